@@ -30,13 +30,13 @@ def generate_auth_header(api_path, http_method, timestamp):
 # Function to query the ThreatConnect Owners API
 def query_owners_api():
     # API endpoint for the owners
-    api_url = f'https://{instance_name}.threatconnect.com/api/v2/owners'
+    api_url = f'https://{instance_name}.threatconnect.com/api/v3/security/owners'
 
     # Generate timestamp
     timestamp = str(int(time.time()))
 
     # Generate Authorization header
-    api_path = '/api/v2/owners'  # Update this path to match the new endpoint
+    api_path = '/api/v3/security/owners'
     auth_header = generate_auth_header(api_path, 'GET', timestamp)
 
     # Headers
@@ -51,8 +51,9 @@ def query_owners_api():
 
     # Check if the request was successful
     if response.status_code == 200:
-        # Print the response JSON if the request was successful
-        print(response.json())
+        # List each owner so its exact name can be used in TQL filters such as ownerName = "<name>"
+        for owner in response.json().get('data', []):
+            print(f"{owner.get('id')}\t{owner.get('name')}\t{owner.get('type')}")
     else:
         # Print the error if the request failed
         print(f"Error: {response.status_code} - {response.text}")
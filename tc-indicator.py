@@ -6,10 +6,19 @@ import os
 import time
 import logging
 from colorama import Fore, Style, init
-from datetime import datetime
+from datetime import datetime, timedelta, timezone
 import urllib.parse
+import functools
+import ipaddress
+from collections import Counter
 import re
 
+# readline gives the indicator prompt shell-style Up/Down history; it isn't available on Windows
+try:
+    import readline
+except ImportError:
+    readline = None
+
 # Initialize colorama
 init(autoreset=True)
 
@@ -24,6 +33,33 @@ if not tc_accessid or not tc_secretkey:
     logging.error("Missing environment variables for Access ID or Secret Key")
     exit(1)
 
+def read_int_env(name, default):
+    value = os.getenv(name, str(default))
+    try:
+        return int(value)
+    except ValueError:
+        logging.error(f"Environment variable {name} must be a whole number, got {value!r}")
+        exit(1)
+
+# Seconds to wait on a slow ThreatConnect instance before giving up on a request
+request_timeout = max(1, read_int_env('TC_REQUEST_TIMEOUT_SECS', 30))
+
+# Retry settings for rate-limited (429) and server-side (5xx) failures
+max_retries = max(0, read_int_env('TC_MAX_RETRIES', 3))
+retry_base_ms = max(0, read_int_env('TC_RETRY_BASE_MS', 500))
+
+# Optional owner to scope every request to, sent as the X-TC-Owner header
+owner_scope = os.getenv('TC_OWNER')
+
+# Indicators not modified for longer than this are flagged as stale
+stale_days = max(0, read_int_env('TC_STALE_DAYS', 365))
+
+# Past searches are kept here so they can be recalled across runs
+history_file = os.path.expanduser('~/.tc_indicator_history')
+history_length = 100
+tql_chunk_size = 50  # Terms per combined summary in (...) query, to keep the URL a sane length
+type_count_limit = 5  # Types listed in the breakdown before the rest are summarized
+
 ioc_patterns = {
     "host": r"(?i)\b((?:(?!-)[a-zA-Z0-9-]{1,63}(?<!-)\.)+(?!apk|apt|arpa|asp|bat|bdoda|bin|bsspx|cer|cfg|cgi|class|close|cpl|cpp|crl|css|dll|doc|docx|dyn|exe|fl|gz|hlp|htm|html|ico|ini|ioc|jar|jpg|js|jxr|lco|lnk|loader|log|lxdns|mdb|mp4|odt|pcap|pdb|pdf|php|plg|plist|png|ppt|pptx|quit|rar|rtf|scr|sleep|ssl|torproject|tmp|txt|vbp|vbs|w32|wav|xls|xlsx|xml|xpi|dat($|\r\n)|gif($|\r\n)|xn$)(?:xn--[a-zA-Z0-9]{2,22}|[a-zA-Z]{2,13}))(?!.*@)",
     "ipv4": r"\b(25[0-5]|2[0-4][0-9]|[01]?[0-9][0-9]?)\.(25[0-5]|2[0-4][0-9]|[01]?[0-9][0-9]?)\.(25[0-5]|2[0-4][0-9]|[01]?[0-9][0-9]?)\.(25[0-5]|2[0-4][0-9]|[01]?[0-9][0-9]?)\b",
@@ -41,112 +77,598 @@ ioc_patterns = {
     "email_subject": r".{1,100}",
 }
 
+# Map your internal indicator types to the expected ThreatConnect API types
+type_mapping = {
+    "ipv4": "Address",
+    "ipv6": "Address",
+    "host": "Host",
+    "email_address": "EmailAddress",
+    "url": "URL",
+    "asn": "ASN",
+    "cidr": "CIDR",
+    "email_subject": "EmailSubject",
+    "mutex": "Mutex",
+    "registry_key": "Registry Key",
+    "user_agent": "User Agent",
+    "md5": "File",
+    "sha-1": "File",
+    "sha-256": "File",
+    # Add other mappings as necessary
+}
+
+# Values of these types routinely contain spaces and commas, e.g. "Mozilla/5.0 (X11; Linux x86_64)"
+free_text_types = {"EmailSubject", "Mutex", "Registry Key", "User Agent"}
+
 def generate_auth_header(api_path, query_string, http_method, timestamp):
     message = f"{api_path}{query_string}:{http_method}:{timestamp}" if query_string else f"{api_path}:{http_method}:{timestamp}"
     signature = base64.b64encode(hmac.new(tc_secretkey.encode(), message.encode(), hashlib.sha256).digest()).decode()
     return f"TC {tc_accessid}:{signature}"
 
+def get_with_retries(instance_name, api_path, query_string):
+    full_url = f'https://{instance_name}.threatconnect.com{api_path}{query_string}'
+    for attempt in range(max_retries + 1):
+        # Sign every attempt separately so the timestamp stays fresh across backoff delays
+        timestamp = str(int(time.time()))
+        auth_header = generate_auth_header(api_path, query_string, 'GET', timestamp)
+        headers = {
+            'Timestamp': timestamp,
+            'Authorization': auth_header,
+            'Accept': 'application/json'
+        }
+        if owner_scope:
+            headers['X-TC-Owner'] = owner_scope
+        response = requests.get(full_url, headers=headers, timeout=request_timeout)
+        transient = response.status_code == 429 or response.status_code >= 500
+        if not transient or attempt == max_retries:
+            break
+        print(Fore.YELLOW + f"Retrying ({attempt + 1}/{max_retries})…")
+        time.sleep(retry_base_ms * 2 ** attempt / 1000)
+    response.raise_for_status()
+    return response
+
+def detect_indicator_type(indicator):
+    # Parse the unambiguous formats properly before falling back to the looser ioc_patterns regexes
+    try:
+        address = ipaddress.ip_address(indicator)
+        return "ipv4" if address.version == 4 else "ipv6"
+    except ValueError:
+        pass
+    if '/' in indicator:
+        try:
+            ipaddress.ip_network(indicator, strict=False)
+            return "cidr"
+        except ValueError:
+            pass
+    if re.fullmatch(r'[a-fA-F0-9]+', indicator):
+        hash_type = {32: "md5", 40: "sha-1", 64: "sha-256"}.get(len(indicator))
+        if hash_type:
+            return hash_type
+    parsed_url = urllib.parse.urlparse(indicator)
+    if parsed_url.scheme and parsed_url.netloc:
+        return "url"
+    if re.fullmatch(ioc_patterns["email_address"], indicator):
+        return "email_address"
+    if re.fullmatch(ioc_patterns["asn"], indicator):
+        return "asn"
+    # Internationalized domains (e.g. bücher.de) are checked in their punycode form
+    try:
+        ascii_indicator = indicator.encode('idna').decode()
+    except UnicodeError:
+        ascii_indicator = indicator
+    if re.fullmatch(ioc_patterns["host"], ascii_indicator):
+        return "host"
+    return None
+
 def determine_indicator_type(indicator):
+    detected_type = detect_indicator_type(indicator)
+    if detected_type:
+        return detected_type
     for ioc_type, pattern in ioc_patterns.items():
         if re.match(pattern, indicator, re.IGNORECASE):
             return ioc_type
     return "unknown"
 
-def construct_tql_query(indicator_type: str, indicator: str) -> str:
-    # Map your internal indicator types to the expected ThreatConnect API types
-    type_mapping = {
-        "ipv4": "Address",
-        "host": "Host",
-        "email_address": "EmailAddress",
-        "url": "URL",
-        "asn": "ASN",
-        "cidr": "CIDR",
-        "email_subject": "EmailSubject",
-        "mutex": "Mutex",
-        "registry_key": "Registry Key",
-        "user_agent": "User Agent",
-        # Add other mappings as necessary
-    }
-    api_indicator_type = type_mapping.get(indicator_type.lower(), "Unknown")
+def refang(value: str) -> str:
+    # Undo common defanging, e.g. "hxxps://evil[.]com" or "192 (.) 168 [.] 1 [.] 1"
+    value = re.sub(r'\s*(?:\[\.\]|\(\.\))\s*', '.', value)
+    value = re.sub(r'\s*\[:\]\s*', ':', value)
+    value = re.sub(r'\s*\[@\]\s*', '@', value)
+    value = re.sub(r'\bhxxp', 'http', value, flags=re.IGNORECASE)
+    return value.strip()
+
+def parse_typed_query(input_string: str):
+    # Split a leading "type:<TypeName>" off the input, e.g. "type:host bad.com" -> ("Host", "bad.com")
+    match = re.match(r'\s*type:\s*(.*)', input_string, re.IGNORECASE | re.DOTALL)
+    if not match:
+        return None, input_string
+    remainder = match.group(1)
+    # Known API type names match in any casing, and the two-word ones ("Registry Key", "User Agent")
+    # also match with a space, an underscore or nothing between the words
+    for name in sorted(set(type_mapping.values()), key=len, reverse=True):
+        name_pattern = r'[\s_]?'.join(re.escape(word) for word in name.split(' '))
+        known_match = re.match(name_pattern + r'(?:\s+|$)(.*)', remainder, re.IGNORECASE | re.DOTALL)
+        if known_match:
+            return name, known_match.group(1)
+    # Anything else (e.g. a custom indicator type) is taken as a single word and passed through
+    custom_match = re.match(r'(\S+)\s*(.*)', remainder, re.DOTALL)
+    if not custom_match:
+        return None, remainder
+    return custom_match.group(1), custom_match.group(2)
+
+def split_terms(input_string: str, type_name=None):
+    # Terms are separated by spaces, commas or line breaks. A free-text type's value is the rest of the line,
+    # so only line breaks separate those.
+    separator = r'\n+' if type_name in free_text_types else r'[,\n\s]+'
+    return [term.strip() for term in re.split(separator, input_string.strip()) if term.strip()]
+
+def tql_escape(value: str) -> str:
+    # Backslashes and double quotes would otherwise end the quoted TQL string early
+    return value.replace('\\', '\\\\').replace('"', '\\"')
+
+def api_type_for(indicator: str, type_name: str = None) -> str:
+    # The ThreatConnect type to search a term as: the type: override if given, otherwise the detected type
+    return type_name or type_mapping.get(determine_indicator_type(indicator).lower(), "Unknown")
+
+def construct_tql_query(api_indicator_type: str, indicators: list) -> str:
+    # One query covers every term of a type, e.g. typeName in ("Host") and summary in ("a.com","b.net")
+    summaries = ",".join(f'"{tql_escape(indicator)}"' for indicator in indicators)
+    api_indicator_type = tql_escape(api_indicator_type)
+
+    # A File's summary joins all of its hashes, so match the hashes against each hash field instead
+    if api_indicator_type == "File":
+        return f'typeName in ("File") and (md5 in ({summaries}) or sha1 in ({summaries}) or sha256 in ({summaries}))'
 
     # Construct the TQL query to filter by both type and summary
-    tql_query = f'typeName in ("{api_indicator_type}") and summary in ("{indicator}")'
+    tql_query = f'typeName in ("{api_indicator_type}") and summary in ({summaries})'
     return tql_query
 
+def matched_terms(records):
+    # Everything a searched term can match in the results: summaries, plus each hash of a File
+    return {value.casefold() for record in records
+            for value in (record.get('summary'), record.get('md5'), record.get('sha1'), record.get('sha256')) if value}
+
+def query_indicator_with_tql(api_indicator_type: str, indicators: list):
+    tql_query = construct_tql_query(api_indicator_type, indicators)
+    fields = ['attributes', 'securityLabels', 'observations']
+    if api_indicator_type == 'Host':
+        fields.extend(['dnsResolution', 'whoIs'])
+    elif api_indicator_type == 'Address':
+        fields.extend(['dnsResolution', 'geoLocation'])
+    return run_tql_query(tql_query, fields)
+
+def run_tql_query(tql_query: str, fields: list):
+    encoded_tql = urllib.parse.quote(tql_query)
+    query_string = f'?tql={encoded_tql}' + ''.join(f'&fields={field}' for field in fields)
+    return send_get_request('/api/v3/indicators', query_string)
+
+def get_indicator_by_id(indicator_id: int):
+    # Fetch a single indicator with every field the TQL lookups request, plus tags and associations.
+    # The type isn't known until the record comes back, so the Host and Address enrichments are always asked for.
+    # Returns the indicator, an empty dict when nothing has that ID, or None when the request failed.
+    fields = ['tags', 'associatedGroups', 'associatedIndicators', 'attributes', 'securityLabels', 'observations',
+              'dnsResolution', 'geoLocation', 'whoIs']
+    query_string = '?' + '&'.join(f'fields={field}' for field in fields)
+    data = send_get_request(f'/api/v3/indicators/{indicator_id}', query_string)
+    if data is None:
+        return None  # The request error has already been printed
+    records = extract_records(data)
+    if records is None:
+        print(Fore.RED + f"Fetching indicator {indicator_id} failed: {describe_failure(data)}")
+        return None
+    if not records:
+        print(Fore.YELLOW + f"No indicator found with ID {indicator_id}")
+        return {}
+    return records[0]
+
+def describe_failure(data):
+    # Error bodies carry the useful explanation in "message"; fall back to the status when there isn't one
+    if not isinstance(data, dict):
+        return "ThreatConnect returned an unexpected response"
+    if data.get('message'):
+        return data['message']
+    if data.get('status', 'Success') != 'Success':
+        return f"ThreatConnect returned status {data.get('status')}"
+    return "ThreatConnect returned no data"
 
-def query_indicator_with_tql(indicator_type: str, indicator: str):
+def extract_records(data):
+    # Single-record endpoints return "data" as an object rather than a list, and some omit "status"
+    if not isinstance(data, dict):
+        return None
+    if data.get('status', 'Success') != 'Success' or data.get('data') is None:
+        return None
+    records = data['data']
+    return records if isinstance(records, list) else [records]
+
+@functools.lru_cache(maxsize=None)
+def get_instance_name():
+    # Asked once per run rather than before every request
+    print("Please provide an instance name. Example: company.threatconnect.com")
+    return input("Instance name: ")
+
+def send_get_request(api_path: str, query_string: str):
     try:
-        # Ensure 'indicator' is defined and passed correctly to this function
-        tql_query = construct_tql_query(indicator_type, indicator)
-        encoded_tql = urllib.parse.quote(tql_query)
-        api_path = '/api/v3/indicators'
-        query_string = f'?tql={encoded_tql}'
-        timestamp = str(int(time.time()))
-        auth_header = generate_auth_header(api_path, query_string, 'GET', timestamp)
-        headers = {
-            'Timestamp': timestamp,
-            'Authorization': auth_header,
-            'Accept': 'application/json'
-        }
-        print("Please provide an instance name. Example: company.threatconnect.com")
-        instance_name=input("Instance name: ")
-        full_url = f'https://{instance_name}.threatconnect.com{api_path}{query_string}'
-        response = requests.get(full_url, headers=headers)
-        response.raise_for_status()
+        response = get_with_retries(get_instance_name(), api_path, query_string)
         return response.json()
+    except requests.exceptions.Timeout:
+        print(Fore.RED + f"Request timed out after {request_timeout}s — check your instance name")
     except requests.exceptions.HTTPError as http_err:
-        print(Fore.RED + f"HTTP error occurred: {http_err.response.status_code} - {http_err.response.text}")
+        # ThreatConnect explains rejected queries (e.g. invalid TQL) in the "message" field of the error body
+        error_message = http_err.response.text
+        try:
+            error_body = http_err.response.json()
+        except ValueError:
+            error_body = None
+        if isinstance(error_body, dict):
+            error_message = error_body.get('message', error_message)
+        print(Fore.RED + f"HTTP error occurred: {http_err.response.status_code} - {error_message}")
     except requests.exceptions.RequestException as req_err:
         print(Fore.RED + f"Request error occurred: {req_err}")
     except Exception as err:
-        # If this line throws the error, ensure 'indicator' is correctly passed to the function
         print(Fore.RED + f"An unexpected error occurred: {err}")
     return None
 
+def parse_timestamp(value):
+    # ThreatConnect timestamps arrive as RFC 3339 with or without milliseconds, or as epoch milliseconds
+    if value is None:
+        return None
+    if isinstance(value, (int, float)):
+        try:
+            return datetime.fromtimestamp(value / 1000, timezone.utc).replace(tzinfo=None)
+        except (OverflowError, OSError, ValueError):
+            return None
+    for timestamp_format in ("%Y-%m-%dT%H:%M:%SZ", "%Y-%m-%dT%H:%M:%S.%fZ", "%Y-%m-%dT%H:%M:%S%z", "%Y-%m-%dT%H:%M:%S.%f%z"):
+        try:
+            parsed = datetime.strptime(value, timestamp_format)
+        except ValueError:
+            continue
+        # Normalize offsets to naive UTC so all timestamps compare the same way
+        if parsed.tzinfo is not None:
+            parsed = parsed.astimezone(timezone.utc).replace(tzinfo=None)
+        return parsed
+    return None
+
+def format_timestamp(value):
+    parsed = parse_timestamp(value)
+    if parsed:
+        return parsed.strftime("%B %d, %Y %H:%M:%S")
+    # Show unrecognized values as-is rather than failing the whole result set
+    return value or 'N/A'
+
+def is_stale(indicator, now=None):
+    last_modified = parse_timestamp(indicator.get('lastModified') or indicator.get('dateAdded'))
+    if last_modified is None:
+        return False
+    now = now or datetime.now(timezone.utc).replace(tzinfo=None)
+    return now - last_modified > timedelta(days=stale_days)
+
+def format_geolocation(geo):
+    # Only the parts the API actually returned are shown, e.g. "US · Phoenix · AS16509 Amazon"
+    if not geo:
+        return ''
+    asn = str(geo.get('asn') or '')
+    if asn and not asn.upper().startswith('AS'):
+        asn = f"AS{asn}"
+    network = ' '.join(part for part in (asn, geo.get('organization')) if part)
+    return ' · '.join(part for part in (geo.get('countryCode'), geo.get('city'), network) if part)
+
+def humanize_size(size):
+    if size < 1024:
+        return f"{size} B"
+    for unit in ("KB", "MB", "GB", "TB"):
+        size /= 1024
+        if size < 1024 or unit == "TB":
+            return f"{size:.1f} {unit}"
+
+# Traffic Light Protocol labels from most to least restrictive, with the color each should stand out in
+tlp_levels = [
+    ("TLP:RED", Fore.RED),
+    ("TLP:AMBER+STRICT", Fore.YELLOW),
+    ("TLP:AMBER", Fore.YELLOW),
+    ("TLP:GREEN", Fore.GREEN),
+    ("TLP:CLEAR", Fore.WHITE),
+    ("TLP:WHITE", Fore.WHITE),
+]
+
+def most_restrictive_label(labels):
+    names = {(label.get('name') or '').upper() for label in labels}
+    for name, color in tlp_levels:
+        if name in names:
+            return name, color
+    return None
+
+def format_security_label(label):
+    # Render a label as a badge on its own color; the API returns the color as a hex string like "FF0033"
+    name = label.get('name') or 'N/A'
+    color = (label.get('color') or '').lstrip('#')
+    if not re.fullmatch(r'[0-9a-fA-F]{6}', color):
+        return f"[{name}]"
+    red, green, blue = (int(color[i:i + 2], 16) for i in (0, 2, 4))
+    # Black text on light backgrounds (TLP:CLEAR white, amber, green), white text on dark ones
+    text = "\033[38;2;0;0;0m" if 0.299 * red + 0.587 * green + 0.114 * blue >= 128 else "\033[38;2;255;255;255m"
+    return f"\033[48;2;{red};{green};{blue}m{text}{Style.BRIGHT} {name} {Style.RESET_ALL}"
+
 def format_and_print_indicator_data(indicator_data):
     for indicator in indicator_data:
-        # Assuming 'dateAdded', 'lastModified', etc., are the correct keys in your data
-        date_added = indicator.get('dateAdded', 'N/A')
-        last_modified = indicator.get('lastModified', 'N/A')
-        summary = indicator.get('summary', 'N/A')
-        # Convert date strings to datetime objects if they are not 'N/A'
-        if date_added != 'N/A':
-            date_added = datetime.strptime(date_added, "%Y-%m-%dT%H:%M:%SZ").strftime("%B %d, %Y %H:%M:%S")
-        if last_modified != 'N/A':
-            last_modified = datetime.strptime(last_modified, "%Y-%m-%dT%H:%M:%SZ").strftime("%B %d, %Y %H:%M:%S")
+        # Some instances omit lastModified or send null; show the creation date instead
+        date_added = format_timestamp(indicator.get('dateAdded'))
+        last_modified = format_timestamp(indicator.get('lastModified') or indicator.get('dateAdded'))
 
         print(f"{Fore.RED}{Style.BRIGHT}Summary:{Style.RESET_ALL} {indicator.get('summary', 'N/A')}")
+        # Put the handling restriction up front so TLP:RED data isn't shared by accident
+        restriction = most_restrictive_label(indicator.get('securityLabels', {}).get('data', []))
+        if restriction:
+            print(f"{Fore.RED}{Style.BRIGHT}Handling:{Style.RESET_ALL} {restriction[1]}{Style.BRIGHT}{restriction[0]}")
         print(f"{Fore.RED}{Style.BRIGHT}Date Added:{Style.RESET_ALL} {date_added}")
-        print(f"{Fore.RED}{Style.BRIGHT}Last Modified:{Style.RESET_ALL} {last_modified}")
+        if indicator.get('firstSeen') or indicator.get('lastSeen'):
+            first_seen = format_timestamp(indicator.get('firstSeen'))
+            last_seen = format_timestamp(indicator.get('lastSeen'))
+            print(f"{Fore.RED}{Style.BRIGHT}Seen:{Style.RESET_ALL} {first_seen} → {last_seen}")
+        stale_marker = f" {Style.DIM}(stale)" if is_stale(indicator) else ""
+        print(f"{Fore.RED}{Style.BRIGHT}Last Modified:{Style.RESET_ALL} {last_modified}{stale_marker}")
+        if indicator.get('source'):
+            print(f"{Fore.RED}{Style.BRIGHT}Source:{Style.RESET_ALL} {indicator['source']}")
         print(f"{Fore.RED}{Style.BRIGHT}Type:{Style.RESET_ALL} {indicator.get('type', 'N/A')}")
-        print(f"{Fore.RED}{Style.BRIGHT}Rating:{Style.RESET_ALL} {'💀' * int(indicator.get('rating', 0))} ({indicator.get('rating', 'N/A')}/5)")
-        print(f"{Fore.RED}{Style.BRIGHT}Confidence:{Style.RESET_ALL} {indicator.get('confidence', 'N/A')}%")
+        # An unset rating or confidence is not the same as zero, so show it as N/A
+        rating = indicator.get('rating')
+        confidence = indicator.get('confidence')
+        rating_text = f"{'💀' * int(rating)} ({rating}/5)" if rating is not None else 'N/A'
+        confidence_text = f"{confidence}%" if confidence is not None else 'N/A'
+        print(f"{Fore.RED}{Style.BRIGHT}Rating:{Style.RESET_ALL} {rating_text}")
+        print(f"{Fore.RED}{Style.BRIGHT}Confidence:{Style.RESET_ALL} {confidence_text}")
+        observations = f"{indicator.get('observations', 'N/A')}"
+        if indicator.get('lastObserved'):
+            observations += f" (last observed {format_timestamp(indicator['lastObserved'])})"
+        print(f"{Fore.RED}{Style.BRIGHT}Observations:{Style.RESET_ALL} {observations}")
         print(f"{Fore.RED}{Style.BRIGHT}Owner:{Style.RESET_ALL} {indicator.get('ownerName', 'N/A')}")
         print(f"{Fore.RED}{Style.BRIGHT}Active:{Style.RESET_ALL} {'Yes' if indicator.get('active', False) else 'No'}")
         print(f"{Fore.RED}{Style.BRIGHT}Web Link:{Style.RESET_ALL} {indicator.get('webLink', 'N/A')}")
+        security_labels = indicator.get('securityLabels', {}).get('data', [])
+        if security_labels:
+            print(f"{Fore.RED}{Style.BRIGHT}Security Labels:{Style.RESET_ALL} {' '.join(format_security_label(label) for label in security_labels)}")
+        tags = indicator.get('tags', {}).get('data', [])
+        if tags:
+            print(f"{Fore.RED}{Style.BRIGHT}Tags:{Style.RESET_ALL} {', '.join(tag.get('name', 'N/A') for tag in tags)}")
         #if 'legacyLink' in indicator:
             # print(f"{Fore.RED}{Style.BRIGHT}Legacy Link:{Style.RESET_ALL} {indicator.get('legacyLink', 'N/A')}")
-        if 'source' in indicator:
-            print(f"{Fore.RED}{Style.BRIGHT}Source:{Style.RESET_ALL} {indicator.get('source', 'N/A')}")
+
+        geo_line = format_geolocation(indicator.get('geoLocation'))
+        if geo_line:
+            print(f"{Fore.RED}{Style.BRIGHT}Geo:{Style.RESET_ALL} {geo_line}")
+        if indicator.get('type') == 'Host':
+            print(f"{Fore.RED}{Style.BRIGHT}DNS Active:{Style.RESET_ALL} {'Yes' if indicator.get('dnsActive', False) else 'No'}")
+            print(f"{Fore.RED}{Style.BRIGHT}WHOIS Active:{Style.RESET_ALL} {'Yes' if indicator.get('whoisActive', False) else 'No'}")
+            whois = indicator.get('whoIs')
+            if whois:
+                print(f"{Fore.RED}{Style.BRIGHT}WHOIS:{Style.RESET_ALL}")
+                print(f"  Registrar: {whois.get('registrar') or 'N/A'}")
+                print(f"  Created: {format_timestamp(whois.get('creationDate'))}")
+                print(f"  Expires: {format_timestamp(whois.get('expiryDate'))}")
+                if whois.get('registrantEmail'):
+                    print(f"  Registrant Email: {whois['registrantEmail']}")
+
+        # Hosts resolve to addresses and addresses to hosts; each resolution lists whichever side applies
+        resolutions = indicator.get('dnsResolution', {}).get('data', [])
+        if resolutions:
+            print(f"{Fore.RED}{Style.BRIGHT}DNS Resolutions:{Style.RESET_ALL}")
+            for resolution in resolutions:
+                resolved_on = format_timestamp(resolution.get('resolutionDate'))
+                for address in resolution.get('addresses', {}).get('data', []):
+                    print(f"  {address.get('ip', 'N/A')} ({resolved_on})")
+                for host in resolution.get('hosts', {}).get('data', []):
+                    print(f"  {host.get('hostName', 'N/A')} ({resolved_on})")
+
+        associated_groups = indicator.get('associatedGroups', {}).get('data', [])
+        if associated_groups:
+            print(f"{Fore.RED}{Style.BRIGHT}Associated Groups:{Style.RESET_ALL}")
+            for group in associated_groups:
+                print(f"  {group.get('type', 'N/A')}: {group.get('name', 'N/A')}")
+        associated_indicators = indicator.get('associatedIndicators', {}).get('data', [])
+        if associated_indicators:
+            print(f"{Fore.RED}{Style.BRIGHT}Associated Indicators:{Style.RESET_ALL}")
+            for associated in associated_indicators:
+                print(f"  {associated.get('type', 'N/A')}: {associated.get('summary', 'N/A')}")
+
+        if indicator.get('type') == 'File':
+            print(f"{Fore.RED}{Style.BRIGHT}Hashes:{Style.RESET_ALL}")
+            for label, key in (("MD5", 'md5'), ("SHA1", 'sha1'), ("SHA256", 'sha256')):
+                if indicator.get(key):
+                    print(f"  {label}: {indicator[key]}")
+            if indicator.get('size') is not None:
+                print(f"  Size: {humanize_size(indicator['size'])}")
 
         description = indicator.get('description', 'No description available.')
         print("\n" + f"{Fore.RED}{Style.BRIGHT}Description:{Style.RESET_ALL}\n{description}\n")
 
+        # Attributes come back wrapped as {"data": [...]} when requested via fields=attributes
+        attributes = indicator.get('attributes', {}).get('data', [])
+        if attributes:
+            print(f"{Fore.RED}{Style.BRIGHT}Attributes:{Style.RESET_ALL}")
+            for attribute in sorted(attributes, key=lambda a: (a.get('type') or '', a.get('dateAdded') or '')):
+                # Indent continuation lines so multi-line values stay under their type
+                value = str(attribute.get('value') or '').replace('\n', '\n    ')
+                print(f"  • {attribute.get('type') or 'N/A'}: {value}")
+                print(f"    {Style.DIM}Added {format_timestamp(attribute.get('dateAdded'))}")
+            print()
+
         print("-" * 40 + "\n")
 
+def print_query_results(data, query):
+    # Returns the indicators found, an empty list when there were none, or None when the search failed
+    if data is None:
+        return None  # The request error has already been printed
+    records = extract_records(data)
+    if records is None:
+        print(Fore.RED + f"Search for '{query}' failed: {describe_failure(data)}")
+        return None
+    if not records:
+        print(Fore.YELLOW + f"No results for '{query}'")
+        return []
+    format_and_print_indicator_data(records)
+    return records
+
+def print_type_counts(results):
+    # e.g. "Types: Address: 12, Host: 8, URL: 3", most common first; the tail is folded into "+N more"
+    type_counts = Counter(indicator.get('type') or 'Unknown' for indicator in results)
+    if type_counts:
+        breakdown = [f"{indicator_type}: {count}" for indicator_type, count in type_counts.most_common(type_count_limit)]
+        if len(type_counts) > type_count_limit:
+            breakdown.append(f"+{len(type_counts) - type_count_limit} more")
+        print(Fore.YELLOW + "Types: " + ", ".join(breakdown))
+
+def print_result_stats(results):
+    # Totals across everything a multi-term or tql: search returned, printed under the type breakdown
+    if not results:
+        return
+    observations = [indicator['observations'] for indicator in results if isinstance(indicator.get('observations'), (int, float))]
+    if observations:
+        print(Fore.YELLOW + f"Observations: {sum(observations)} total, {max(observations)} max")
+    stale_count = sum(1 for indicator in results if is_stale(indicator))
+    if stale_count:
+        print(Fore.YELLOW + f"Stale: {stale_count} not modified in over {stale_days} days")
+    restricted_count = 0
+    for indicator in results:
+        restriction = most_restrictive_label((indicator.get('securityLabels') or {}).get('data') or [])
+        if restriction and restriction[0] in ("TLP:RED", "TLP:AMBER+STRICT", "TLP:AMBER"):
+            restricted_count += 1
+    if restricted_count:
+        print(Fore.YELLOW + f"TLP:RED/AMBER: {restricted_count}")
+    countries = {(indicator.get('geoLocation') or {}).get('countryCode') for indicator in results} - {None, ''}
+    if countries:
+        print(Fore.YELLOW + f"Countries: {len(countries)} ({', '.join(sorted(countries))})")
+    first_seen = [parse_timestamp(indicator.get('firstSeen')) for indicator in results]
+    first_seen = [timestamp for timestamp in first_seen if timestamp]
+    if first_seen:
+        print(Fore.YELLOW + f"Earliest First Seen: {min(first_seen).strftime('%B %d, %Y %H:%M:%S')}")
+
+def average_by_owner(results, field):
+    # Mean of one numeric field per owner; indicators without a value are left out
+    values = {}
+    for indicator in results:
+        if indicator.get(field) is not None:
+            values.setdefault(indicator.get('ownerName') or 'Unknown', []).append(indicator[field])
+    return {owner: sum(owner_values) / len(owner_values) for owner, owner_values in values.items()}
+
+def owner_breakdown(results):
+    # (owner, indicator count, average rating, average confidence) per owner, most indicators first.
+    # Averages are None for an owner with no rated (or no scored) indicators.
+    counts = Counter(indicator.get('ownerName') or 'Unknown' for indicator in results)
+    ratings = average_by_owner(results, 'rating')
+    confidences = average_by_owner(results, 'confidence')
+    return [(owner, count, ratings.get(owner), confidences.get(owner)) for owner, count in counts.most_common()]
+
+def print_owner_breakdown(results):
+    # e.g. "Owners: Acme Intel: 12 (avg rating 3.2, avg confidence 75), Partner Feed: 4 (avg rating N/A, avg confidence N/A)"
+    breakdown = owner_breakdown(results)
+    if breakdown:
+        print(Fore.YELLOW + "Owners: " + ", ".join(
+            f"{owner}: {count} (avg rating {'N/A' if rating is None else f'{rating:.1f}'}, "
+            f"avg confidence {'N/A' if confidence is None else f'{confidence:.0f}'})"
+            for owner, count, rating, confidence in breakdown))
+
+def load_search_history():
+    if readline is None:
+        return
+    readline.set_history_length(history_length)
+    try:
+        readline.read_history_file(history_file)
+    except OSError:
+        pass  # No history yet
+
+def save_search_history():
+    if readline is None:
+        return
+    try:
+        readline.write_history_file(history_file)
+    except OSError as err:
+        logging.warning(f"Could not save search history: {err}")
+
 def main():
+    load_search_history()
     input_string = input("Enter indicators (separated by space, line, or comma): ")
-    indicators = re.split(r'[,\n\s]+', input_string.strip())
+    # Save now so later prompts (e.g. the instance name) don't end up in the search history
+    save_search_history()
+
+    # "tql:" sends the rest of the line to ThreatConnect verbatim, e.g. tql: rating >= 4 and confidence >= 75
+    if input_string.strip().lower().startswith('tql:'):
+        tql_query = input_string.strip()[len('tql:'):].strip()
+        # An empty tql= filter would list every indicator the account can see
+        if not tql_query:
+            print(Fore.YELLOW + "No TQL query entered.")
+            return
+        print(Fore.YELLOW + f"Running raw TQL: {tql_query}")
+        results = print_query_results(run_tql_query(tql_query, ['attributes', 'securityLabels', 'observations']), tql_query) or []
+        print_type_counts(results)
+        print_owner_breakdown(results)
+        print_result_stats(results)
+        return
+
+    # Analysts often paste defanged IOCs; search for the live form instead
+    refanged_input = refang(input_string)
+    if refanged_input != input_string.strip():
+        print(Fore.YELLOW + f"Searching as {refanged_input} (refanged)")
+    input_string = refanged_input
+
+    # "type:<TypeName>" at the start overrides type detection for every indicator on the line
+    type_name, input_string = parse_typed_query(input_string)
+    indicators = split_terms(input_string, type_name)
+    if not indicators:
+        print(Fore.YELLOW + "No indicators entered.")
+        return
+    not_found = []
+    failed = []
+    results = []
+    terms_by_type = {}
 
     for indicator in indicators:
-        if indicator:  # Ensure the indicator is not empty
-            indicator_type = determine_indicator_type(indicator)
-            print(Fore.YELLOW + f"Processing Indicator: {indicator}, Type: {indicator_type}")
-            data = query_indicator_with_tql(indicator_type, indicator)  # Correctly pass 'indicator' here
-            if data and 'data' in data and data['status'] == 'Success':
-                format_and_print_indicator_data(data['data'])
-            else:
-                print(Fore.RED + "No data returned from the query or an error occurred.")
+        # "id:<number>" fetches that indicator's complete record instead of searching by summary
+        id_match = re.fullmatch(r'id:(\d+)', indicator, re.IGNORECASE)
+        if not id_match:
+            terms_by_type.setdefault(api_type_for(indicator, type_name), []).append(indicator)
+            continue
+        print(Fore.YELLOW + f"Fetching Indicator ID: {id_match.group(1)}")
+        full_indicator = get_indicator_by_id(int(id_match.group(1)))
+        # A failed request says nothing about whether the indicator exists, so keep it apart from misses
+        if full_indicator is None:
+            failed.append(indicator)
+        elif not full_indicator:
+            not_found.append(indicator)
+        else:
+            format_and_print_indicator_data([full_indicator])
+            results.append(full_indicator)
+
+    # Terms of the same type are searched together, a chunk at a time, then matched back by summary
+    for api_indicator_type, terms in terms_by_type.items():
+        for start in range(0, len(terms), tql_chunk_size):
+            chunk = terms[start:start + tql_chunk_size]
+            print(Fore.YELLOW + f"Processing Indicator{'s' if len(chunk) > 1 else ''}: {', '.join(chunk)}, Type: {api_indicator_type}")
+            found = print_query_results(query_indicator_with_tql(api_indicator_type, chunk), ', '.join(chunk))
+            if found is None:
+                failed.extend(chunk)
+                continue
+            results.extend(found)
+            matched = matched_terms(found)
+            not_found.extend(term for term in chunk if term.casefold() not in matched)
+
+    if len(indicators) > 1:
+        matched = len(indicators) - len(not_found) - len(failed)
+        print(Fore.YELLOW + f"Queried {len(indicators)} terms, {matched} matched, {len(not_found)} not found, {len(failed)} failed")
+        for heading, terms in (("Not Found", not_found), ("Failed", failed)):
+            if terms:
+                print(f"{Fore.RED}{Style.BRIGHT}{heading}:{Style.RESET_ALL}")
+                for indicator in terms:
+                    print(f"  {indicator}")
+        print_type_counts(results)
+        print_owner_breakdown(results)
+        print_result_stats(results)
 
 if __name__ == "__main__":
-    main()
+    try:
+        main()
+    except KeyboardInterrupt:
+        # Ctrl+C at a prompt or mid-request: exit quietly with the usual SIGINT status instead of dumping a traceback
+        print(Style.RESET_ALL + "\nInterrupted.")
+        exit(130)
+    except EOFError:
+        # Ctrl+D (or piped input running out) at a prompt just ends the session
+        print(Style.RESET_ALL)
+        exit(0)
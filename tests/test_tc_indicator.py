@@ -0,0 +1,223 @@
+import contextlib
+from datetime import datetime, timedelta
+import io
+import importlib.util
+import os
+import unittest
+
+# The script refuses to load without credentials; the helpers under test never use them
+os.environ.setdefault('tc_accessid', 'test-access-id')
+os.environ.setdefault('tc_secretkey', 'test-secret-key')
+
+# tc-indicator.py can't be imported by name because of the hyphen
+spec = importlib.util.spec_from_file_location(
+    'tc_indicator', os.path.join(os.path.dirname(__file__), '..', 'tc-indicator.py'))
+tc_indicator = importlib.util.module_from_spec(spec)
+spec.loader.exec_module(tc_indicator)
+
+
+class TqlEscapeTest(unittest.TestCase):
+    def test_escapes_double_quotes(self):
+        self.assertEqual(tc_indicator.tql_escape('say "hi"'), 'say \\"hi\\"')
+
+    def test_escapes_backslashes_before_quotes(self):
+        self.assertEqual(tc_indicator.tql_escape('HKEY_LOCAL_MACHINE\\Run'), 'HKEY_LOCAL_MACHINE\\\\Run')
+        self.assertEqual(tc_indicator.tql_escape('\\"'), '\\\\\\"')
+
+    def test_leaves_percent_alone(self):
+        # Lookups use exact "in" matches, so % is not a wildcard and goes through unchanged
+        self.assertEqual(tc_indicator.tql_escape('http://a.com/%20x'), 'http://a.com/%20x')
+
+
+class ConstructTqlQueryTest(unittest.TestCase):
+    def test_combines_terms_into_one_summary_in(self):
+        self.assertEqual(tc_indicator.construct_tql_query('Host', ['a.com', 'b.net']),
+                         'typeName in ("Host") and summary in ("a.com","b.net")')
+
+    def test_file_hashes_match_each_hash_field(self):
+        self.assertEqual(tc_indicator.construct_tql_query('File', ['aa', 'bb']),
+                         'typeName in ("File") and (md5 in ("aa","bb") or sha1 in ("aa","bb") or sha256 in ("aa","bb"))')
+
+    def test_matched_terms_cover_summaries_and_hashes(self):
+        records = [{'summary': 'Bad.com'}, {'summary': 'AA : BB', 'md5': 'AA', 'sha1': 'BB'}]
+        self.assertEqual(tc_indicator.matched_terms(records), {'bad.com', 'aa : bb', 'aa', 'bb'})
+
+
+class ParseTypedQueryTest(unittest.TestCase):
+    def test_without_prefix_returns_input_unchanged(self):
+        self.assertEqual(tc_indicator.parse_typed_query('bad.com evil.net'), (None, 'bad.com evil.net'))
+
+    def test_missing_type_name(self):
+        self.assertEqual(tc_indicator.parse_typed_query('type:'), (None, ''))
+        self.assertEqual(tc_indicator.parse_typed_query('type:   '), (None, ''))
+
+    def test_type_name_is_case_insensitive(self):
+        self.assertEqual(tc_indicator.parse_typed_query('TYPE:host bad.com'), ('Host', 'bad.com'))
+        self.assertEqual(tc_indicator.parse_typed_query('type: registry_key HKLM\\Run'), ('Registry Key', 'HKLM\\Run'))
+
+    def test_only_the_first_colon_starts_the_type(self):
+        self.assertEqual(tc_indicator.parse_typed_query('type:URL http://a.com:8080/x'), ('URL', 'http://a.com:8080/x'))
+        self.assertEqual(tc_indicator.parse_typed_query('type:Custom:Thing value'), ('Custom:Thing', 'value'))
+
+
+class SplitTermsTest(unittest.TestCase):
+    def test_splits_on_spaces_commas_and_newlines(self):
+        self.assertEqual(tc_indicator.split_terms('a.com, b.com  c.com\n1.2.3.4,'), ['a.com', 'b.com', 'c.com', '1.2.3.4'])
+
+    def test_free_text_types_keep_the_whole_line(self):
+        self.assertEqual(tc_indicator.split_terms('Mozilla/5.0 (X11; Linux, x86_64)', 'User Agent'), ['Mozilla/5.0 (X11; Linux, x86_64)'])
+        self.assertEqual(tc_indicator.split_terms('Invoice due\nYour parcel', 'EmailSubject'), ['Invoice due', 'Your parcel'])
+
+
+class DetectIndicatorTypeTest(unittest.TestCase):
+    def test_ipv6(self):
+        self.assertEqual(tc_indicator.detect_indicator_type('2001:db8::1'), 'ipv6')
+        self.assertEqual(tc_indicator.detect_indicator_type('::1'), 'ipv6')
+
+    def test_sha256(self):
+        self.assertEqual(tc_indicator.detect_indicator_type('e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855'), 'sha-256')
+
+    def test_punycode_and_unicode_hosts(self):
+        self.assertEqual(tc_indicator.detect_indicator_type('xn--bcher-kva.de'), 'host')
+        self.assertEqual(tc_indicator.detect_indicator_type('bücher.de'), 'host')
+
+
+class IsStaleTest(unittest.TestCase):
+    last_modified = datetime(2024, 1, 1)
+
+    def check(self, age):
+        return tc_indicator.is_stale({'lastModified': '2024-01-01T00:00:00Z'}, now=self.last_modified + age)
+
+    def test_exactly_at_threshold_is_not_stale(self):
+        self.assertFalse(self.check(timedelta(days=tc_indicator.stale_days)))
+
+    def test_past_threshold_is_stale(self):
+        self.assertTrue(self.check(timedelta(days=tc_indicator.stale_days, seconds=1)))
+
+    def test_missing_timestamp_is_not_stale(self):
+        self.assertFalse(tc_indicator.is_stale({}, now=self.last_modified))
+
+
+class ExtractRecordsTest(unittest.TestCase):
+    def test_array_payload(self):
+        data = {'status': 'Success', 'data': [{'id': 1}, {'id': 2}]}
+        self.assertEqual(tc_indicator.extract_records(data), [{'id': 1}, {'id': 2}])
+
+    def test_object_payload_without_status(self):
+        self.assertEqual(tc_indicator.extract_records({'data': {'id': 1}}), [{'id': 1}])
+
+    def test_failures(self):
+        self.assertIsNone(tc_indicator.extract_records({'status': 'Failure', 'message': 'Invalid TQL'}))
+        self.assertIsNone(tc_indicator.extract_records({'status': 'Success'}))
+        self.assertIsNone(tc_indicator.extract_records({'status': 'Success', 'data': None}))
+        self.assertIsNone(tc_indicator.extract_records(['not', 'an', 'object']))
+
+
+class PrintTypeCountsTest(unittest.TestCase):
+    def output(self, results):
+        buffer = io.StringIO()
+        with contextlib.redirect_stdout(buffer):
+            tc_indicator.print_type_counts(results)
+        return buffer.getvalue()
+
+    def test_mixed_types_most_common_first(self):
+        results = [{'type': 'Host'}, {'type': 'Address'}, {'type': 'Host'}, {'type': 'URL'}, {'type': 'Host'}, {'type': 'Address'}]
+        self.assertIn('Types: Host: 3, Address: 2, URL: 1', self.output(results))
+
+    def test_missing_or_null_type_counts_as_unknown(self):
+        self.assertIn('Types: Unknown: 2, Host: 1', self.output([{'type': 'Host'}, {}, {'type': None}]))
+
+    def test_no_results_prints_nothing(self):
+        self.assertEqual(self.output([]), '')
+
+    def test_caps_at_five_types(self):
+        results = [{'type': indicator_type} for indicator_type in ['Host'] * 3 + ['Address'] * 2 + ['URL', 'File', 'ASN', 'CIDR', 'Mutex']]
+        self.assertIn('Types: Host: 3, Address: 2, URL: 1, File: 1, ASN: 1, +2 more', self.output(results))
+
+
+class PrintResultStatsTest(unittest.TestCase):
+    def output(self, results):
+        buffer = io.StringIO()
+        with contextlib.redirect_stdout(buffer):
+            tc_indicator.print_result_stats(results)
+        return buffer.getvalue()
+
+    def test_observation_totals(self):
+        self.assertIn('Observations: 13 total, 10 max', self.output([{'observations': 3}, {'observations': 10}, {'observations': None}]))
+
+    def test_stale_count(self):
+        results = [{'lastModified': '2000-01-01T00:00:00Z'}, {'lastModified': datetime.now().strftime('%Y-%m-%dT%H:%M:%SZ')}]
+        self.assertIn(f'Stale: 1 not modified in over {tc_indicator.stale_days} days', self.output(results))
+
+    def test_red_and_amber_count(self):
+        results = [
+            {'securityLabels': {'data': [{'name': 'TLP:RED'}]}},
+            {'securityLabels': {'data': [{'name': 'TLP:GREEN'}, {'name': 'TLP:AMBER+STRICT'}]}},
+            {'securityLabels': {'data': [{'name': 'TLP:GREEN'}]}},
+            {'securityLabels': None},
+        ]
+        self.assertIn('TLP:RED/AMBER: 2', self.output(results))
+
+    def test_distinct_countries(self):
+        results = [{'geoLocation': {'countryCode': 'US'}}, {'geoLocation': {'countryCode': 'DE'}},
+                   {'geoLocation': {'countryCode': 'US'}}, {'geoLocation': None}, {}]
+        self.assertIn('Countries: 2 (DE, US)', self.output(results))
+
+    def test_earliest_first_seen(self):
+        results = [{'firstSeen': '2023-05-01T00:00:00Z'}, {'firstSeen': '2021-02-03T04:05:06Z'}, {'firstSeen': None}]
+        self.assertIn('Earliest First Seen: February 03, 2021 04:05:06', self.output(results))
+
+    def test_no_results_prints_nothing(self):
+        self.assertEqual(self.output([]), '')
+
+
+class OwnerBreakdownTest(unittest.TestCase):
+    def test_counts_and_averages_across_three_owners(self):
+        results = [
+            {'ownerName': 'Acme Intel', 'rating': 3, 'confidence': 70},
+            {'ownerName': 'Partner Feed'},
+            {'ownerName': 'Acme Intel', 'rating': 4},
+            {'ownerName': 'Community', 'rating': 5, 'confidence': 90},
+            {'ownerName': 'Acme Intel', 'confidence': 80},
+            {'ownerName': 'Community', 'rating': 2, 'confidence': 60},
+        ]
+        self.assertEqual(tc_indicator.owner_breakdown(results), [
+            ('Acme Intel', 3, 3.5, 75),
+            ('Community', 2, 3.5, 75),
+            ('Partner Feed', 1, None, None),
+        ])
+
+class MostRestrictiveLabelTest(unittest.TestCase):
+    def test_picks_the_most_restrictive(self):
+        labels = [{'name': 'TLP:GREEN'}, {'name': 'tlp:amber'}, {'name': 'Internal'}]
+        self.assertEqual(tc_indicator.most_restrictive_label(labels)[0], 'TLP:AMBER')
+
+    def test_null_name_is_skipped(self):
+        self.assertIsNone(tc_indicator.most_restrictive_label([{'name': None}]))
+        self.assertEqual(tc_indicator.most_restrictive_label([{'name': None}, {'name': 'TLP:RED'}])[0], 'TLP:RED')
+
+
+class FormatSecurityLabelTest(unittest.TestCase):
+    def test_text_contrasts_with_the_background(self):
+        self.assertIn('38;2;0;0;0m', tc_indicator.format_security_label({'name': 'TLP:CLEAR', 'color': 'FFFFFF'}))
+        self.assertIn('38;2;0;0;0m', tc_indicator.format_security_label({'name': 'TLP:AMBER', 'color': 'FFC000'}))
+        self.assertIn('38;2;255;255;255m', tc_indicator.format_security_label({'name': 'TLP:RED', 'color': 'FF0033'}))
+
+    def test_missing_or_malformed_color_is_plain(self):
+        self.assertEqual(tc_indicator.format_security_label({'name': 'TLP:RED', 'color': None}), '[TLP:RED]')
+        self.assertEqual(tc_indicator.format_security_label({'name': 'TLP:RED', 'color': 'F03'}), '[TLP:RED]')
+
+
+class WhoisRenderingTest(unittest.TestCase):
+    def test_whois_section_is_read_from_whoIs(self):
+        host = {'type': 'Host', 'summary': 'bad.com', 'whoisActive': True, 'whoIs': {
+            'registrar': 'Example Registrar', 'creationDate': '2020-01-02T00:00:00Z', 'expiryDate': '2030-01-02T00:00:00Z'}}
+        buffer = io.StringIO()
+        with contextlib.redirect_stdout(buffer):
+            tc_indicator.format_and_print_indicator_data([host])
+        self.assertIn('Registrar: Example Registrar', buffer.getvalue())
+        self.assertIn('Created: January 02, 2020', buffer.getvalue())
+
+
+if __name__ == '__main__':
+    unittest.main()